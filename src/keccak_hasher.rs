@@ -10,6 +10,9 @@ impl KeccakHasher {
 
 impl H for KeccakHasher {
     type Hash = [u8; 32];
+    // Keccak is byte-oriented, so its "field" element is just the 32-byte
+    // digest.
+    type Field = [u8; 32];
 
     fn hash(&self, data: &[u8]) -> Self::Hash {
         let mut keccak = Keccak::v256();
@@ -23,4 +26,26 @@ impl H for KeccakHasher {
     fn output_size(&self) -> usize {
         32
     }
+
+    fn hash_fields(&self, domain: u64, inputs: &[Self::Field]) -> Self::Field {
+        let mut keccak = Keccak::v256();
+        keccak.update(&domain.to_be_bytes());
+        for input in inputs {
+            keccak.update(input);
+        }
+        let mut out: [u8; 32] = [0; 32];
+        keccak.finalize(&mut out);
+        out
+    }
+
+    fn field_to_bytes(&self, f: &Self::Field) -> Vec<u8> {
+        f.to_vec()
+    }
+
+    fn bytes_to_field(&self, bytes: &[u8]) -> Self::Field {
+        let mut out: [u8; 32] = [0; 32];
+        let len = bytes.len().min(32);
+        out[..len].copy_from_slice(&bytes[..len]);
+        out
+    }
 }
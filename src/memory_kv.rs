@@ -0,0 +1,85 @@
+use super::{KvStore, WriteBatch};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+/// An in-memory [`KvStore`] backed by a `HashMap`, with interior mutability so
+/// it satisfies the `&self` signatures of the trait. Useful for unit tests and
+/// short-lived proving workloads that should not touch disk or collide on
+/// RocksDB paths.
+///
+/// The map lives behind an `Rc`, so cloning a `MemoryStore` yields another
+/// handle onto the *same* data — handy for reopening a tree over a store it was
+/// previously written through.
+#[derive(Default, Clone)]
+pub struct MemoryStore {
+    map: Rc<RefCell<HashMap<Vec<u8>, Vec<u8>>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl KvStore for MemoryStore {
+    fn get(&self, k: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self.map.borrow().get(k).cloned())
+    }
+
+    fn insert(&self, k: &[u8], v: &[u8]) -> anyhow::Result<()> {
+        self.map.borrow_mut().insert(k.to_vec(), v.to_vec());
+        Ok(())
+    }
+
+    fn delete(&self, k: &[u8]) -> anyhow::Result<()> {
+        self.map.borrow_mut().remove(k);
+        Ok(())
+    }
+
+    fn begin_batch(&self) -> anyhow::Result<Box<dyn WriteBatch + '_>> {
+        Ok(Box::new(MemoryWriteBatch {
+            store: self,
+            ops: Vec::new(),
+        }))
+    }
+}
+
+enum MemoryOp {
+    Put(Vec<u8>, Vec<u8>),
+    Delete(Vec<u8>),
+}
+
+/// A [`WriteBatch`] over a [`MemoryStore`]; mutations are staged and applied to
+/// the map together on `commit`.
+pub struct MemoryWriteBatch<'a> {
+    store: &'a MemoryStore,
+    ops: Vec<MemoryOp>,
+}
+
+impl<'a> WriteBatch for MemoryWriteBatch<'a> {
+    fn put(&mut self, k: &[u8], v: &[u8]) -> anyhow::Result<()> {
+        self.ops.push(MemoryOp::Put(k.to_vec(), v.to_vec()));
+        Ok(())
+    }
+
+    fn delete(&mut self, k: &[u8]) -> anyhow::Result<()> {
+        self.ops.push(MemoryOp::Delete(k.to_vec()));
+        Ok(())
+    }
+
+    fn commit(self: Box<Self>) -> anyhow::Result<()> {
+        let mut map = self.store.map.borrow_mut();
+        for op in self.ops {
+            match op {
+                MemoryOp::Put(k, v) => {
+                    map.insert(k, v);
+                }
+                MemoryOp::Delete(k) => {
+                    map.remove(&k);
+                }
+            }
+        }
+        Ok(())
+    }
+}
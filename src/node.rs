@@ -20,6 +20,16 @@ impl TryFrom<&[u8]> for Node {
 }
 
 impl Node {
+    /// Serializes the node for storage. `Empty` is encoded as zero bytes to
+    /// round-trip with the `TryFrom<&[u8]>` impl above; every other variant is
+    /// tagged and encoded by `bincode`.
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        match self {
+            Node::Empty => Ok(vec![]),
+            _ => bincode::serialize(self).map_err(|e| e.into()),
+        }
+    }
+
     pub fn is_leaf(&self) -> bool {
         match self {
             Node::LeafNode { path: _, value: _ } => true,
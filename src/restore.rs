@@ -0,0 +1,84 @@
+use super::tree_hasher::Hasher;
+use super::{KvStore, SparseMerkleTree};
+
+/// The 32-byte (for Keccak) hash identifying a committed tree version.
+pub type RootHash = Vec<u8>;
+
+/// Rebuilds a [`SparseMerkleTree`] from batches of sorted `(path, value)` pairs
+/// and verifies the reconstructed root against an expected `target_root` in
+/// [`finish`].
+///
+/// # Trust model
+///
+/// This is a *reconstruction* helper, not an untrusted-peer sync protocol. The
+/// integrity guarantee comes entirely from [`finish`](Restore::finish), which
+/// recomputes the root over everything ingested and rejects the result unless
+/// it equals `target_root`; any tampered or missing leaf changes the root and
+/// is caught there. [`add_chunk`](Restore::add_chunk) itself only enforces
+/// chunk ordering — it does **not** authenticate individual chunks as they
+/// arrive, so a caller must treat the data as unverified until `finish`
+/// succeeds. (A per-chunk range proof folding the already-restored left
+/// frontier, the chunk, and a right frontier into the root would let chunks be
+/// rejected incrementally; that is intentionally out of scope here.)
+///
+/// [`finish`]: Restore::finish
+pub struct Restore<H: Hasher, K: KvStore> {
+    tree: SparseMerkleTree<H, K>,
+    target_root: RootHash,
+    /// Path of the last leaf ingested, used to enforce strictly increasing
+    /// order across and within chunks.
+    last_path: Option<Vec<u8>>,
+}
+
+impl<H: Hasher, K: KvStore> Restore<H, K> {
+    pub fn new(tree: SparseMerkleTree<H, K>, target_root: RootHash) -> Self {
+        Self {
+            tree,
+            target_root,
+            last_path: None,
+        }
+    }
+
+    /// Ingests a chunk of leaves, sorted by path and strictly greater than every
+    /// previously ingested path.
+    ///
+    /// The chunk's contents are not verified here; integrity of the whole stream
+    /// is only established once [`finish`](Restore::finish) confirms the
+    /// reconstructed root equals the target (see the [trust model](Restore)).
+    pub fn add_chunk(&mut self, chunk: &[(Vec<u8>, Vec<u8>)]) -> anyhow::Result<()> {
+        if chunk.is_empty() {
+            return Err(anyhow::anyhow!("cannot add an empty chunk"));
+        }
+
+        // Enforce strictly increasing path order across the whole stream.
+        let mut prev = self.last_path.clone();
+        for (path, _) in chunk {
+            if let Some(p) = &prev {
+                if path <= p {
+                    return Err(anyhow::anyhow!("chunk paths must be strictly increasing"));
+                }
+            }
+            prev = Some(path.clone());
+        }
+
+        for (path, value) in chunk {
+            self.tree.update_at_path(path, value)?;
+        }
+
+        self.last_path = chunk.last().map(|(p, _)| p.clone());
+        Ok(())
+    }
+
+    /// Finalizes the restore, returning the reconstructed root after checking it
+    /// matches the expected target. This is the sole integrity check — a
+    /// mismatch means some ingested leaf was wrong or missing.
+    pub fn finish(self) -> anyhow::Result<RootHash> {
+        let root = self.tree.root().to_vec();
+        if root != self.target_root {
+            return Err(anyhow::anyhow!(
+                "restored root does not match the target root"
+            ));
+        }
+        Ok(root)
+    }
+}
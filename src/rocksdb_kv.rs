@@ -1,5 +1,5 @@
-use super::KvStore;
-use rocksdb::DB;
+use super::{KvStore, WriteBatch};
+use rocksdb::{WriteBatch as RocksWriteBatch, DB};
 
 pub struct RocksDbStore {
     db: DB,
@@ -13,14 +13,8 @@ impl RocksDbStore {
 }
 
 impl KvStore for RocksDbStore {
-    fn get(&self, k: &[u8]) -> anyhow::Result<Vec<u8>> {
-        self.db.get(k).map_err(|e| e.into()).and_then(|r| {
-            if let Some(r) = r {
-                Ok(r)
-            } else {
-                Err(anyhow::anyhow!("Key record {:?} does not exist!", k))
-            }
-        })
+    fn get(&self, k: &[u8]) -> anyhow::Result<Option<Vec<u8>>> {
+        self.db.get(k).map_err(|e| e.into())
     }
 
     fn insert(&self, k: &[u8], v: &[u8]) -> anyhow::Result<()> {
@@ -31,4 +25,34 @@ impl KvStore for RocksDbStore {
     fn delete(&self, k: &[u8]) -> anyhow::Result<()> {
         self.db.delete(k).map_err(|e| e.into())
     }
+
+    fn begin_batch(&self) -> anyhow::Result<Box<dyn WriteBatch + '_>> {
+        Ok(Box::new(RocksDbWriteBatch {
+            db: &self.db,
+            batch: RocksWriteBatch::default(),
+        }))
+    }
+}
+
+/// A [`WriteBatch`] backed by `rocksdb::WriteBatch`, so every staged mutation
+/// is applied to the database in a single atomic write on `commit`.
+pub struct RocksDbWriteBatch<'a> {
+    db: &'a DB,
+    batch: RocksWriteBatch,
+}
+
+impl<'a> WriteBatch for RocksDbWriteBatch<'a> {
+    fn put(&mut self, k: &[u8], v: &[u8]) -> anyhow::Result<()> {
+        self.batch.put(k, v);
+        Ok(())
+    }
+
+    fn delete(&mut self, k: &[u8]) -> anyhow::Result<()> {
+        self.batch.delete(k);
+        Ok(())
+    }
+
+    fn commit(self: Box<Self>) -> anyhow::Result<()> {
+        self.db.write(self.batch).map_err(|e| e.into())
+    }
 }
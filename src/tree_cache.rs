@@ -0,0 +1,50 @@
+use super::KvStore;
+use std::collections::{HashMap, HashSet};
+
+/// Pending mutations of a copy-on-write cache. `inserts` override the backing
+/// store and `deletes` mask it, so the overlay can be staged and then either
+/// flushed or discarded wholesale.
+#[derive(Default)]
+pub struct CacheLayer {
+    pub inserts: HashMap<Vec<u8>, Vec<u8>>,
+    pub deletes: HashSet<Vec<u8>>,
+}
+
+impl CacheLayer {
+    pub fn insert(&mut self, k: &[u8], v: &[u8]) {
+        self.deletes.remove(k);
+        self.inserts.insert(k.to_vec(), v.to_vec());
+    }
+
+    pub fn delete(&mut self, k: &[u8]) {
+        self.inserts.remove(k);
+        self.deletes.insert(k.to_vec());
+    }
+
+    /// Resolves `k` against the overlay alone:
+    /// - `Some(Some(v))` — overridden to `v`,
+    /// - `Some(None)` — masked as deleted,
+    /// - `None` — untouched, fall through to the store.
+    pub fn get(&self, k: &[u8]) -> Option<Option<Vec<u8>>> {
+        if let Some(v) = self.inserts.get(k) {
+            return Some(Some(v.clone()));
+        }
+        if self.deletes.contains(k) {
+            return Some(None);
+        }
+        None
+    }
+
+    /// Flushes the overlay to `store` through a single [`WriteBatch`] so the
+    /// whole layer lands atomically.
+    pub fn flush<K: KvStore>(self, store: &K) -> anyhow::Result<()> {
+        let mut batch = store.begin_batch()?;
+        for (k, v) in &self.inserts {
+            batch.put(k, v)?;
+        }
+        for k in &self.deletes {
+            batch.delete(k)?;
+        }
+        batch.commit()
+    }
+}
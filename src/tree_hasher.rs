@@ -1,7 +1,36 @@
 pub trait Hasher {
     type Hash: Copy + PartialEq + Into<Vec<u8>> + TryFrom<Vec<u8>>;
+
+    /// The element the hasher natively operates on. For byte-oriented hashers
+    /// (e.g. Keccak) this is just the byte digest; for algebraic, circuit
+    /// friendly hashers (e.g. Poseidon/Pedersen over BN254) it is a prime-field
+    /// element. Generalizing over it lets a SNARK backend plug in without the
+    /// tree assuming byte digests.
+    ///
+    /// NOTE: this is only the first step. Tree traversal — `sidenodes`,
+    /// `_update`, `_delete`, and proof (de)construction — still operates on the
+    /// byte digests produced by [`hash`](Self::hash), not on `Field`. The
+    /// field-element helpers ([`hash_fields`](Self::hash_fields),
+    /// [`field_to_bytes`](Self::field_to_bytes),
+    /// [`bytes_to_field`](Self::bytes_to_field), and the `*_field` methods on
+    /// [`TreeHasher`](crate::tree_hasher::TreeHasher)) are not yet threaded into
+    /// that path, so a Poseidon/BN254 backend cannot drive the tree or emit
+    /// circuit-reproducible proofs end-to-end through this API today.
+    type Field: Clone + PartialEq;
+
     fn hash(&self, data: &[u8]) -> Self::Hash;
     fn output_size(&self) -> usize;
+
+    /// Hashes a sequence of field elements under a domain separator. Leaf and
+    /// internal nodes pass distinct domains so the two hashing rules stay
+    /// independent — exactly what a Noir/circom verifier must reproduce.
+    fn hash_fields(&self, domain: u64, inputs: &[Self::Field]) -> Self::Field;
+
+    /// Lowers a field element to its canonical byte encoding.
+    fn field_to_bytes(&self, f: &Self::Field) -> Vec<u8>;
+
+    /// Lifts a byte encoding into a field element.
+    fn bytes_to_field(&self, bytes: &[u8]) -> Self::Field;
 }
 
 #[derive(Clone)]
@@ -13,6 +42,10 @@ pub struct TreeHasher<H: Hasher> {
 impl<H: Hasher> TreeHasher<H> {
     const NODE_PREFIX: [u8; 1] = [1];
     const LEAF_PREFIX: [u8; 1] = [0];
+    /// Domain separators for the field-element hashing variants. They mirror
+    /// the byte prefixes above so that leaf and internal hashing never collide.
+    const LEAF_DOMAIN: u64 = 0;
+    const NODE_DOMAIN: u64 = 1;
 
     pub fn new(hasher: H) -> Self {
         let zero_hash = vec![0; hasher.output_size()];
@@ -41,23 +74,22 @@ impl<H: Hasher> TreeHasher<H> {
         (self.hasher.hash(&data).into(), data)
     }
 
-    pub fn parse_leaf(&self, data: &[u8]) -> (Vec<u8>, Vec<u8>) {
-        (
-            data[Self::LEAF_PREFIX.len()..self.hasher.output_size() + Self::LEAF_PREFIX.len()]
-                .to_vec(),
-            data[self.hasher.output_size() + Self::LEAF_PREFIX.len()..].to_vec(),
-        )
+    pub fn is_leaf(&self, data: &[u8]) -> bool {
+        data[..Self::LEAF_PREFIX.len()] == Self::LEAF_PREFIX
     }
 
-    pub fn parse_node(&self, data: &[u8]) -> (Vec<u8>, Vec<u8>) {
-        (
-            data[Self::NODE_PREFIX.len()..self.hasher.output_size() + Self::LEAF_PREFIX.len()]
-                .to_vec(),
-            data[self.hasher.output_size() + Self::LEAF_PREFIX.len()..].to_vec(),
-        )
+    /// Field-element variant of [`digest_leaf`](Self::digest_leaf): hashes the
+    /// `path`/`value` field elements under the leaf domain. Used by algebraic
+    /// backends whose proofs are recomputed inside a circuit.
+    pub fn digest_leaf_field(&self, path: &H::Field, value: &H::Field) -> H::Field {
+        self.hasher
+            .hash_fields(Self::LEAF_DOMAIN, &[path.clone(), value.clone()])
     }
 
-    pub fn is_leaf(&self, data: &[u8]) -> bool {
-        data[..Self::LEAF_PREFIX.len()] == Self::LEAF_PREFIX
+    /// Field-element variant of [`digest_node`](Self::digest_node): hashes the
+    /// two child field elements under the internal-node domain.
+    pub fn digest_node_field(&self, left: &H::Field, right: &H::Field) -> H::Field {
+        self.hasher
+            .hash_fields(Self::NODE_DOMAIN, &[left.clone(), right.clone()])
     }
 }
@@ -20,6 +20,11 @@ pub fn common_prefix(v1: &[u8], v2: &[u8]) -> usize {
 
 pub fn set_msb_at(data: &mut Vec<u8>, position: usize) {
     let index = position / 8;
+    // Grow the buffer so callers (e.g. compact-proof bitmasks) can start from an
+    // empty `Vec` and set arbitrary positions without pre-sizing it.
+    if index >= data.len() {
+        data.resize(index + 1, 0);
+    }
     data[index] |= 1 << (7 - (position % 8));
 }
 